@@ -1,38 +1,301 @@
-use tauri::Manager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tauri_plugin_shell::process::CommandChild;
+
+/// Restart backoff schedule; the last entry repeats once exhausted.
+const RESTART_BACKOFF_MS: &[u64] = &[250, 500, 1000, 2000, 4000];
+/// Consecutive fast failures allowed before the supervisor gives up.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+/// Uptime after which a crash is treated as a fresh failure streak
+/// instead of a continuation of the previous one.
+const STABLE_UPTIME: Duration = Duration::from_secs(30);
+/// Marker line `media-organizer-backend` prints on stdout once it is
+/// listening and ready to accept requests.
+const BACKEND_READY_MARKER: &str = "MEDIA_ORGANIZER_BACKEND_READY";
+/// How long to wait for the readiness marker before giving up on startup.
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+const MAIN_WINDOW_LABEL: &str = "main";
+/// Log target the sidecar's stdout/stderr lines are tagged with, so they
+/// show up alongside host app logs instead of in a separate stream.
+const BACKEND_LOG_TARGET: &str = "backend";
+/// Release log file cap before rotation, and how many rotated files to keep.
+const LOG_MAX_FILE_SIZE: u128 = 10 * 1024 * 1024;
+const LOG_RETAINED_FILES: usize = 5;
+
+/// Backend child handle kept in managed state so commands and the
+/// sidecar event task can both reach it.
+struct BackendState(Mutex<Option<CommandChild>>);
+
+/// Tracks the current restart backoff streak for the backend sidecar.
+struct RestartState(Mutex<RestartInfo>);
+
+#[derive(Default)]
+struct RestartInfo {
+    consecutive_failures: u32,
+    last_spawn: Option<Instant>,
+}
+
+/// Set once the app starts tearing down, so a backend exit observed during
+/// shutdown (e.g. the `kill()` in the `ExitRequested` handler) doesn't spawn
+/// a fresh, unsupervised backend process.
+struct ShutdownState(AtomicBool);
+
+/// Set once the main window has actually been shown. A restart that
+/// happens before this is true (the backend crashed before printing the
+/// readiness marker) still needs the readiness handshake, not just the
+/// very first spawn.
+struct WindowShownState(AtomicBool);
+
+/// Write a line to the backend sidecar's stdin.
+#[tauri::command]
+fn send_to_backend(state: tauri::State<BackendState>, line: String) -> Result<(), String> {
+    let mut guard = state.0.lock().unwrap();
+    let child = guard.as_mut().ok_or("backend sidecar is not running")?;
+    child
+        .write(format!("{line}\n").as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+/// Spawn the backend sidecar and watch its event stream, restarting it
+/// with exponential backoff if it dies. `needs_ready_handshake` gates the
+/// readiness handshake: true until the main window has actually been
+/// shown, so a crash before the backend ever printed its readiness marker
+/// still re-arms the handshake on restart instead of leaving the window
+/// hidden forever.
+#[cfg(not(debug_assertions))]
+fn spawn_backend_sidecar(app: AppHandle, needs_ready_handshake: bool) {
+    use tauri_plugin_shell::process::CommandEvent;
+    use tauri_plugin_shell::ShellExt;
+
+    // Wired up before the fallible calls below. If creating or spawning the
+    // sidecar fails, those error paths take `ready_tx` and report the
+    // startup failure themselves (the timeout thread then sees a dropped
+    // sender and stays quiet), instead of leaving the window hidden with no
+    // feedback.
+    let mut ready_tx = if needs_ready_handshake {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || match rx.recv_timeout(READY_TIMEOUT) {
+            Ok(()) => show_main_window(&app),
+            Err(RecvTimeoutError::Timeout) => handle_ready_timeout(&app),
+            // The sidecar died before printing the ready marker, dropping
+            // `ready_tx` without a real timeout elapsing. The restart
+            // supervisor (`handle_backend_exit`) is deciding whether to try
+            // again; if it gives up it reports the failure itself, and if
+            // it retries the new spawn gets its own readiness watcher.
+            Err(RecvTimeoutError::Disconnected) => {}
+        });
+        Some(tx)
+    } else {
+        None
+    };
+
+    let sidecar = match app.shell().sidecar("media-organizer-backend") {
+        Ok(sidecar) => sidecar,
+        Err(err) => {
+            log::error!("Failed to create backend sidecar command: {err}");
+            if ready_tx.take().is_some() {
+                handle_ready_timeout(&app);
+            }
+            return;
+        }
+    };
+
+    let (mut rx, child) = match sidecar.spawn() {
+        Ok(pair) => pair,
+        Err(err) => {
+            log::error!("Failed to spawn backend sidecar: {err}");
+            if ready_tx.take().is_some() {
+                handle_ready_timeout(&app);
+            }
+            return;
+        }
+    };
+
+    *app.state::<BackendState>().0.lock().unwrap() = Some(child);
+    app.state::<RestartState>().0.lock().unwrap().last_spawn = Some(Instant::now());
+
+    // Close the race with `ExitRequested`: if shutdown started in the gap
+    // between that handler's one-shot kill and the child being stored
+    // above, this freshly spawned sidecar would otherwise never be killed.
+    if app.state::<ShutdownState>().0.load(Ordering::SeqCst) {
+        if let Some(child) = app.state::<BackendState>().0.lock().unwrap().take() {
+            let _ = child.kill();
+        }
+        return;
+    }
+
+    log::info!("Backend sidecar started");
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(line) => {
+                    let line = String::from_utf8_lossy(&line).to_string();
+                    if line.contains(BACKEND_READY_MARKER) {
+                        if let Some(tx) = ready_tx.take() {
+                            let _ = tx.send(());
+                        }
+                    }
+                    log::info!(target: BACKEND_LOG_TARGET, "{line}");
+                    let _ = app.emit("backend://stdout", line);
+                }
+                CommandEvent::Stderr(line) => {
+                    let line = String::from_utf8_lossy(&line).to_string();
+                    log::warn!(target: BACKEND_LOG_TARGET, "{line}");
+                    let _ = app.emit("backend://stderr", line);
+                }
+                CommandEvent::Terminated(payload) => {
+                    let _ = app.emit("backend://terminated", payload.code);
+                    handle_backend_exit(app.clone(), payload.code);
+                    break;
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Show the (until now hidden) main window and announce that the backend
+/// is ready to receive requests.
+#[cfg(not(debug_assertions))]
+fn show_main_window(app: &AppHandle) {
+    app.state::<WindowShownState>().0.store(true, Ordering::SeqCst);
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) {
+        let _ = window.show();
+    }
+    let _ = app.emit("backend://ready", ());
+    log::info!("Backend sidecar is ready");
+}
+
+/// The backend never printed its readiness marker within `READY_TIMEOUT`
+/// (or the sidecar could not be spawned, or the restart supervisor gave
+/// up); surface the failure with both an
+/// event for the frontend and a native error dialog, since at this point
+/// the main window is still hidden and can't show anything itself.
+#[cfg(not(debug_assertions))]
+fn handle_ready_timeout(app: &AppHandle) {
+    log::error!("Backend sidecar did not become ready within {READY_TIMEOUT:?}");
+    let _ = app.emit("backend://startup-failed", ());
+    app.dialog()
+        .message("The media-organizer backend did not start in time. Please restart the app.")
+        .kind(MessageDialogKind::Error)
+        .title("Startup failed")
+        .show(|_| {});
+}
+
+/// Decide whether to restart the backend sidecar after it exits, applying
+/// exponential backoff and giving up after too many fast failures in a row.
+#[cfg(not(debug_assertions))]
+fn handle_backend_exit(app: AppHandle, exit_code: Option<i32>) {
+    if app.state::<ShutdownState>().0.load(Ordering::SeqCst) {
+        log::info!("Backend sidecar exited during shutdown, not restarting");
+        return;
+    }
+
+    let restart_state = app.state::<RestartState>();
+    let mut info = restart_state.0.lock().unwrap();
+
+    if info
+        .last_spawn
+        .is_some_and(|t| t.elapsed() >= STABLE_UPTIME)
+    {
+        info.consecutive_failures = 0;
+    }
+    info.consecutive_failures += 1;
+    let attempt = info.consecutive_failures;
+    drop(info);
+
+    log::warn!("Backend sidecar exited (code={exit_code:?}), attempt {attempt}");
+    let _ = app.emit("backend://crashed", exit_code);
+
+    if attempt > MAX_CONSECUTIVE_FAILURES {
+        log::error!("Backend sidecar failed {attempt} times in a row, giving up");
+        if !app.state::<WindowShownState>().0.load(Ordering::SeqCst) {
+            handle_ready_timeout(&app);
+        }
+        return;
+    }
+
+    let delay_ms = RESTART_BACKOFF_MS[(attempt as usize - 1).min(RESTART_BACKOFF_MS.len() - 1)];
+    tauri::async_runtime::spawn_blocking(move || {
+        std::thread::sleep(Duration::from_millis(delay_ms));
+        if app.state::<ShutdownState>().0.load(Ordering::SeqCst) {
+            log::info!("App is shutting down, skipping backend restart");
+            return;
+        }
+        let _ = app.emit("backend://restarted", attempt);
+        let needs_ready_handshake = !app.state::<WindowShownState>().0.load(Ordering::SeqCst);
+        spawn_backend_sidecar(app, needs_ready_handshake);
+    });
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let app = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(BackendState(Mutex::new(None)))
+        .manage(RestartState(Mutex::new(RestartInfo::default())))
+        .manage(ShutdownState(AtomicBool::new(false)))
+        .manage(WindowShownState(AtomicBool::new(false)))
+        .invoke_handler(tauri::generate_handler![send_to_backend])
         .setup(|app| {
-            // Enable logging in debug mode
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+            // Debug builds log to the console; release builds log to a
+            // rotating file under the app's log dir, since a media
+            // pipeline that shells out to a sidecar needs diagnostics in
+            // production too. Backend stdout/stderr is tagged with the
+            // `backend` target so it lands in the same stream.
+            use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+            let log_builder = if cfg!(debug_assertions) {
+                tauri_plugin_log::Builder::new()
+                    .clear_targets()
+                    .target(Target::new(TargetKind::Stdout))
+                    .level(log::LevelFilter::Info)
+            } else {
+                tauri_plugin_log::Builder::new()
+                    .clear_targets()
+                    .target(Target::new(TargetKind::LogDir { file_name: None }))
+                    .level(log::LevelFilter::Warn)
+                    .level_for(BACKEND_LOG_TARGET, log::LevelFilter::Info)
+                    .max_file_size(LOG_MAX_FILE_SIZE)
+                    .rotation_strategy(RotationStrategy::KeepSome(LOG_RETAINED_FILES))
+            };
+            app.handle().plugin(log_builder.build())?;
 
-            // Spawn the backend sidecar in production
+            // Spawn the backend sidecar in production, keeping the main
+            // window hidden until the readiness handshake completes.
             #[cfg(not(debug_assertions))]
             {
-                use tauri_plugin_shell::ShellExt;
-                
-                let sidecar = app
-                    .shell()
-                    .sidecar("media-organizer-backend")
-                    .expect("Failed to create sidecar command");
-                
-                let (mut _rx, _child) = sidecar
-                    .spawn()
-                    .expect("Failed to spawn backend sidecar");
-                
-                log::info!("Backend sidecar started");
+                if let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) {
+                    let _ = window.hide();
+                }
+                spawn_backend_sidecar(app.handle().clone(), true);
             }
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application");
+
+    app.run(|app_handle, event| {
+        // Make sure the backend dies with the app instead of being orphaned.
+        if let tauri::RunEvent::ExitRequested { .. } = event {
+            // Set before killing so the sidecar event task's Terminated
+            // handler sees the flag and skips scheduling a restart.
+            app_handle
+                .state::<ShutdownState>()
+                .0
+                .store(true, Ordering::SeqCst);
+            if let Some(child) = app_handle.state::<BackendState>().0.lock().unwrap().take() {
+                let _ = child.kill();
+            }
+        }
+    });
 }